@@ -0,0 +1,194 @@
+use std::fmt;
+
+use crate::query::ast::Directive;
+
+/// Implemented by AST nodes that can render themselves back to GraphQL source
+/// through a [`Formatter`].
+pub trait Displayable {
+    fn display(&self, f: &mut Formatter);
+}
+
+/// Controls how a [`Displayable`] renders: indentation width, a compact
+/// single-line mode for wire transport, and whether a trailing newline is
+/// emitted. Construct with `Style::default()` for human-readable output or
+/// `Style::compact()` for the minified form used for sub-operation strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    indent: u32,
+    compact: bool,
+    trailing_newline: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            indent: 2,
+            compact: false,
+            trailing_newline: true,
+        }
+    }
+}
+
+impl Style {
+    /// A single-line style with no insignificant whitespace, producing output
+    /// like `{me{name}}` -- the shape `FetchNode.operation` sub-queries use.
+    /// Callers that need to minify an AST (e.g. a cache key) should use this
+    /// instead of hand-rolling their own whitespace stripping.
+    pub fn compact() -> Self {
+        Style {
+            indent: 0,
+            compact: true,
+            trailing_newline: false,
+        }
+    }
+
+    /// Sets the number of spaces used per indentation level. Ignored in
+    /// compact mode.
+    pub fn indent(mut self, width: u32) -> Self {
+        self.indent = width;
+        self
+    }
+
+    /// Toggles compact single-line rendering.
+    pub fn compact_mode(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Toggles whether `Formatter::into_string` appends a trailing newline.
+    pub fn trailing_newline(mut self, enabled: bool) -> Self {
+        self.trailing_newline = enabled;
+        self
+    }
+
+    fn is_compact(&self) -> bool {
+        self.compact
+    }
+}
+
+/// Accumulates rendered GraphQL source according to a [`Style`].
+pub struct Formatter<'a> {
+    buf: String,
+    style: &'a Style,
+    indent: u32,
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new(style: &'a Style) -> Formatter<'a> {
+        Formatter {
+            buf: String::new(),
+            style,
+            indent: 0,
+        }
+    }
+
+    pub fn margin(&mut self) {
+        if !self.style.is_compact() && !self.buf.is_empty() {
+            self.buf.push('\n');
+        }
+    }
+
+    pub fn indent(&mut self) {
+        if self.style.is_compact() {
+            return;
+        }
+        for _ in 0..self.indent {
+            self.buf.push(' ');
+        }
+    }
+
+    pub fn endline(&mut self) {
+        if !self.style.is_compact() {
+            self.buf.push('\n');
+        }
+    }
+
+    /// Separates two sibling selection-set items (fields, inline fragments, or
+    /// fragment spreads) from each other. In the default style the items'
+    /// own `margin`/`indent`/`endline` calls already put a line break and
+    /// indentation between them, so this is a no-op; in compact mode none of
+    /// that whitespace exists, so without this a field with no arguments or
+    /// sub-selection would run straight into its sibling's name with nothing
+    /// between them (`idname` instead of `id name` -- a single bogus field,
+    /// not two). Callers write it between items, not after the last one.
+    pub fn item_sep(&mut self) {
+        if self.style.is_compact() {
+            self.buf.push(' ');
+        }
+    }
+
+    pub fn start_block(&mut self) {
+        self.buf.push('{');
+        if !self.style.is_compact() {
+            self.buf.push('\n');
+        }
+        self.indent += self.style.indent;
+    }
+
+    pub fn end_block(&mut self) {
+        self.indent = self.indent.saturating_sub(self.style.indent);
+        self.indent();
+        self.buf.push('}');
+        if !self.style.is_compact() {
+            self.buf.push('\n');
+        }
+    }
+
+    pub fn write(&mut self, s: &str) {
+        self.buf.push_str(s);
+    }
+
+    /// Writes a single insignificant space, e.g. between a selection set's
+    /// name/directives and its opening `{`. Suppressed in compact mode, so
+    /// callers that always want a space (like argument separators) should use
+    /// `write(", ")` instead.
+    pub fn space(&mut self) {
+        if !self.style.is_compact() {
+            self.buf.push(' ');
+        }
+    }
+
+    pub fn write_quoted(&mut self, s: &str) {
+        self.buf.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.buf.push_str("\\\""),
+                '\\' => self.buf.push_str("\\\\"),
+                '\n' => self.buf.push_str("\\n"),
+                c => self.buf.push(c),
+            }
+        }
+        self.buf.push('"');
+    }
+
+    pub fn into_string(self) -> String {
+        let mut buf = self.buf;
+        if self.style.trailing_newline && !self.style.is_compact() && !buf.ends_with('\n') {
+            buf.push('\n');
+        }
+        buf
+    }
+}
+
+pub(crate) fn format_directives<'a>(directives: &[Directive<'a>], f: &mut Formatter) {
+    for directive in directives {
+        f.write(" ");
+        directive.display(f);
+    }
+}
+
+/// Generates a `fmt::Display` impl for an AST node by rendering it with
+/// `Style::default()`. Each module that invokes this macro provides its own
+/// private `to_string` helper, so the rule set it runs through stays local to
+/// the AST it's formatting (query vs. schema).
+macro_rules! impl_display {
+    ($lt:tt $( $typ:ident ),+ $(,)*) => {
+        $(
+            impl<$lt> fmt::Display for $typ<$lt> {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str(&to_string(self))
+                }
+            }
+        )+
+    };
+}
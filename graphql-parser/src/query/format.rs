@@ -23,6 +23,20 @@ fn to_string<T: Displayable>(v: &T) -> String {
     formatter.into_string()
 }
 
+/// Displays every selection-set item, separating siblings with
+/// `Formatter::item_sep` so compact mode doesn't run adjacent leaf fields
+/// together (see its doc comment).
+fn display_selection_items<T: Displayable>(items: &[T], f: &mut Formatter) {
+    let mut first = true;
+    for item in items {
+        if !first {
+            f.item_sep();
+        }
+        first = false;
+        item.display(f);
+    }
+}
+
 impl<'a> Displayable for Document<'a> {
     fn display(&self, f: &mut Formatter) {
         for item in &self.definitions {
@@ -50,11 +64,9 @@ impl<'a> Displayable for FragmentDefinition<'a> {
         f.write(" on ");
         f.write(self.type_condition);
         format_directives(&self.directives, f);
-        f.write(" ");
+        f.space();
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        display_selection_items(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -65,9 +77,7 @@ impl<'a> Displayable for SelectionSet<'a> {
         f.margin();
         f.indent();
         f.start_block();
-        for item in &self.items {
-            item.display(f);
-        }
+        display_selection_items(&self.items, f);
         f.end_block();
     }
 }
@@ -78,9 +88,7 @@ impl<'a> Displayable for SelectionSetRef<'a> {
         f.margin();
         f.indent();
         f.start_block();
-        for item in &self.items {
-            item.display(f);
-        }
+        display_selection_items(&self.items, f);
         f.end_block();
     }
 }
@@ -134,11 +142,9 @@ macro_rules! field_impl {
         format_arguments(&$self.arguments, $f);
         format_directives(&$self.directives, $f);
         if !$self.selection_set.items.is_empty() {
-            $f.write(" ");
+            $f.space();
             $f.start_block();
-            for item in &$self.selection_set.items {
-                item.display($f);
-            }
+            display_selection_items(&$self.selection_set.items, $f);
             $f.end_block();
         } else {
             $f.endline();
@@ -177,11 +183,9 @@ impl<'a> Displayable for OperationDefinition<'a> {
             }
         }
         format_directives(&self.directives, f);
-        f.write(" ");
+        f.space();
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        display_selection_items(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -269,11 +273,9 @@ macro_rules! inline_fragment_impl {
             $f.write(cond);
         }
         format_directives(&$self.directives, $f);
-        $f.write(" ");
+        $f.space();
         $f.start_block();
-        for item in &$self.selection_set.items {
-            item.display($f);
-        }
+        display_selection_items(&$self.selection_set.items, $f);
         $f.end_block();
     };
 }
@@ -336,3 +338,40 @@ impl_display!(
     FieldRef,
     InlineFragmentRef,
 );
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_query;
+    use crate::Style;
+
+    #[test]
+    fn compact_mode_separates_adjacent_leaf_fields() {
+        // Regression test: adjacent leaf fields with no arguments or
+        // sub-selection used to run together with no separator in compact
+        // mode, so `{ id name }` and a single field named `idname` rendered
+        // identically.
+        let doc = parse_query("{ id name }").unwrap();
+        assert_eq!(doc.format(&Style::compact()), "{id name}");
+    }
+
+    #[test]
+    fn compact_mode_separates_leaf_field_from_following_nested_field() {
+        let doc = parse_query("{ id me { name } }").unwrap();
+        assert_eq!(doc.format(&Style::compact()), "{id me{name}}");
+    }
+
+    #[test]
+    fn compact_mode_does_not_add_trailing_separator_before_closing_brace() {
+        let doc = parse_query("{ id }").unwrap();
+        assert_eq!(doc.format(&Style::compact()), "{id}");
+    }
+
+    #[test]
+    fn compact_mode_separates_inline_fragment_siblings() {
+        let doc = parse_query("{ entity { ... on A { id } ... on B { id } } }").unwrap();
+        assert_eq!(
+            doc.format(&Style::compact()),
+            "{entity{... on A{id} ... on B{id}}}"
+        );
+    }
+}
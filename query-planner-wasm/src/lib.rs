@@ -1,34 +1,164 @@
 extern crate wasm_bindgen;
 
-use apollo_query_planner::QueryPlanner;
+use apollo_query_planner::{QueryPlanError, QueryPlanner, QueryPlanningOptionsBuilder};
+use graphql_parser::ParseError;
 use js_sys::JsString;
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 static mut SCHEMA: Vec<String> = vec![];
 static mut DATA: Vec<QueryPlanner> = vec![];
 
+#[derive(Serialize, Clone)]
+struct PlanErrorDetail {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+#[derive(Serialize, Clone)]
+struct PlanErrors {
+    errors: Vec<PlanErrorDetail>,
+}
+
+/// Set by `getQueryPlanner` when schema parsing fails, since it can only
+/// return a raw `usize` pointer and so has no way to carry a diagnostic back
+/// to JS itself. Callers should check `getLastSchemaError` whenever
+/// `getQueryPlanner` returns `0`.
+static mut LAST_SCHEMA_ERROR: Option<PlanErrors> = None;
+
+/// `ParseError`'s `Display` includes the failure position as `... line L, column C`
+/// (it wraps combine's parser errors), but the error itself doesn't expose `line`/
+/// `column` as fields -- so we recover them from that text instead of reporting
+/// `0, 0` for every parse failure, which would send callers hunting for a syntax
+/// error at the start of their document no matter where it actually was.
+fn parse_pos_from_error(err: &ParseError) -> (usize, usize) {
+    let text = err.to_string();
+    let line = text
+        .splitn(2, "line ")
+        .nth(1)
+        .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+        .and_then(|digits| digits.parse().ok())
+        .unwrap_or(0);
+    let column = text
+        .splitn(2, "column ")
+        .nth(1)
+        .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+        .and_then(|digits| digits.parse().ok())
+        .unwrap_or(0);
+    (line, column)
+}
+
+/// Flattens any `QueryPlanError` into the `{ errors: [...] }` shape JS callers expect,
+/// so a bad schema/query surfaces as a diagnostic instead of an unreachable panic.
+fn to_plan_errors(err: QueryPlanError) -> PlanErrors {
+    let detail = match err {
+        QueryPlanError::FailedParsingSchema(e) => {
+            let (line, column) = parse_pos_from_error(&e);
+            PlanErrorDetail {
+                line,
+                column,
+                message: format!("failed parsing schema: {}", e),
+            }
+        }
+        QueryPlanError::FailedParsingQuery(e) => {
+            let (line, column) = parse_pos_from_error(&e);
+            PlanErrorDetail {
+                line,
+                column,
+                message: format!("failed parsing query: {}", e),
+            }
+        }
+        QueryPlanError::InvalidQuery(message) => PlanErrorDetail {
+            line: 0,
+            column: 0,
+            message,
+        },
+        QueryPlanError::ValidationFailed(messages) => {
+            return PlanErrors {
+                errors: messages
+                    .into_iter()
+                    .map(|message| PlanErrorDetail {
+                        line: 0,
+                        column: 0,
+                        message,
+                    })
+                    .collect(),
+            }
+        }
+        QueryPlanError::PlanningError { pos, message } => PlanErrorDetail {
+            line: pos.line,
+            column: pos.column,
+            message,
+        },
+    };
+    PlanErrors {
+        errors: vec![detail],
+    }
+}
+
+/// Parses `schema` and stashes the resulting planner, returning a pointer for
+/// `getQueryPlan` to use. Returns `0` (never a valid pointer) on a malformed
+/// schema instead of panicking/trapping the WASM instance -- callers must
+/// check `getLastSchemaError` when they see a `0` back.
 #[wasm_bindgen(js_name = getQueryPlanner)]
 pub fn get_query_planner(schema: JsString) -> usize {
     unsafe {
+        let schema = String::from(schema);
+        let planner = match QueryPlanner::new(&schema) {
+            Ok(planner) => planner,
+            Err(err) => {
+                LAST_SCHEMA_ERROR = Some(to_plan_errors(err));
+                return 0;
+            }
+        };
+        LAST_SCHEMA_ERROR = None;
+
         if SCHEMA.is_empty() {
-            SCHEMA.push(String::from(schema));
-            DATA.push(QueryPlanner::new(&SCHEMA[0]));
+            SCHEMA.push(schema);
+            DATA.push(planner);
         } else {
-            SCHEMA[0] = String::from(schema);
-            DATA[0] = QueryPlanner::new(&SCHEMA[0]);
+            SCHEMA[0] = schema;
+            DATA[0] = planner;
         }
         let data = &DATA[0];
         data as *const QueryPlanner as usize
     }
 }
 
+/// Returns the diagnostic recorded by the most recent failing `getQueryPlanner`
+/// call, or `null` if it succeeded.
+#[wasm_bindgen(js_name = getLastSchemaError)]
+pub fn get_last_schema_error() -> JsValue {
+    unsafe {
+        match &LAST_SCHEMA_ERROR {
+            Some(errors) => JsValue::from_serde(errors).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+}
+
 #[wasm_bindgen(js_name = getQueryPlan)]
-pub fn get_query_plan(planner_ptr: usize, query: &str) -> JsValue {
+pub fn get_query_plan(
+    planner_ptr: usize,
+    query: &str,
+    operation_name: Option<String>,
+    variables: JsValue,
+) -> JsValue {
     unsafe {
         let planner = planner_ptr as *const QueryPlanner;
         let planner: &QueryPlanner = &*planner;
-        let plan = planner.plan(query, false).unwrap();
-        JsValue::from_serde(&plan).unwrap()
+        let variables = variables.into_serde().unwrap_or_default();
+        let options = QueryPlanningOptionsBuilder::default()
+            .auto_fragmentization(false)
+            .operation_name(operation_name)
+            .variables(variables)
+            .build()
+            .unwrap();
+        match planner.plan(query, options) {
+            Ok(plan) => JsValue::from_serde(&plan).unwrap(),
+            Err(err) => JsValue::from_serde(&to_plan_errors(err)).unwrap(),
+        }
     }
 }
 
@@ -54,7 +184,7 @@ mod tests {
             })),
         };
 
-        let result = get_query_plan(planner, query);
+        let result = get_query_plan(planner, query, None, JsValue::NULL);
         let plan = result.into_serde::<QueryPlan>().unwrap();
         assert_eq!(plan, expected);
     }
@@ -0,0 +1,159 @@
+//! An LRU-bounded cache of previously computed query plans.
+//!
+//! Planning is pure over `(schema, query, options)`, so callers that replan the
+//! same operation repeatedly -- like the WASM layer, which keeps a single global
+//! planner alive across calls -- can skip straight to a cached `QueryPlan`
+//! instead of re-running parsing, validation, and the builder every time.
+
+use crate::model::QueryPlan;
+use crate::QueryPlanningOptions;
+use graphql_parser::query::Document;
+use graphql_parser::Style;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Computes a stable signature for `query` + `operation_name` + `options`,
+/// canonicalized through the existing `Displayable`/`Formatter` machinery (rather
+/// than comparing raw source text) so insignificant whitespace never affects the
+/// cache key.
+///
+/// Deliberately excludes `options.variables`: a `QueryPlan` only depends on the
+/// *shape* of the operation -- its selections, argument/variable *types*, and
+/// declared defaults (all already part of the canonical AST text below) -- not
+/// on the runtime values a caller happens to bind those variables to. Hashing
+/// the values too would give every distinct set of inputs (e.g. a different id)
+/// its own cache entry, defeating the point of memoizing a pure function of
+/// `(schema, query, options)`.
+pub fn signature(query: &Document, operation_name: Option<&str>, options: &QueryPlanningOptions) -> u64 {
+    // The same compact style the builder uses for `FetchNode.operation` strings --
+    // reusing it here means the cache key is only as sensitive to formatting
+    // differences as the generated sub-operations themselves are.
+    let canonical = query.format(&Style::compact());
+
+    let mut hasher = DefaultHasher::new();
+    canonical.hash(&mut hasher);
+    operation_name.hash(&mut hasher);
+    options.auto_fragmentization.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A fixed-capacity, least-recently-used cache of `QueryPlan`s keyed by
+/// [`signature`].
+pub struct PlanCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    entries: HashMap<u64, QueryPlan>,
+    // Most-recently-used key is at the back.
+    order: VecDeque<u64>,
+}
+
+impl PlanCache {
+    pub fn new(capacity: usize) -> Self {
+        PlanCache {
+            capacity,
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+        }
+    }
+
+    pub fn get(&self, key: u64) -> Option<QueryPlan> {
+        let mut inner = self.inner.lock().unwrap();
+        let plan = inner.entries.get(&key).cloned()?;
+        inner.order.retain(|k| *k != key);
+        inner.order.push_back(key);
+        Some(plan)
+    }
+
+    pub fn insert(&self, key: u64, plan: QueryPlan) {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.entries.contains_key(&key) && inner.entries.len() >= self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.order.retain(|k| *k != key);
+        inner.order.push_back(key);
+        inner.entries.insert(key, plan);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FetchNode, PlanNode};
+    use graphql_parser::parse_query;
+
+    fn plan(operation: &str) -> QueryPlan {
+        QueryPlan {
+            node: Some(PlanNode::Fetch(FetchNode {
+                service_name: String::from("accounts"),
+                requires: None,
+                variable_usages: vec![],
+                operation: String::from(operation),
+            })),
+        }
+    }
+
+    #[test]
+    fn signature_distinguishes_structurally_different_documents() {
+        // Regression test for a bug where `Style::compact()` ran adjacent leaf
+        // fields together with no separator, so `{ id name }` and a single
+        // field literally named `idname` canonicalized to the same string.
+        let two_fields = parse_query("{ id name }").unwrap();
+        let one_field = parse_query("{ idname }").unwrap();
+        let options = QueryPlanningOptions::default();
+
+        assert_ne!(
+            signature(&two_fields, None, &options),
+            signature(&one_field, None, &options)
+        );
+    }
+
+    #[test]
+    fn signature_ignores_variable_values() {
+        let query = parse_query("query($id: ID!) { user(id: $id) { name } }").unwrap();
+        let mut with_one = QueryPlanningOptions::default();
+        with_one.variables = serde_json::json!({ "id": "1" });
+        let mut with_other = QueryPlanningOptions::default();
+        with_other.variables = serde_json::json!({ "id": "2" });
+
+        assert_eq!(
+            signature(&query, None, &with_one),
+            signature(&query, None, &with_other)
+        );
+    }
+
+    #[test]
+    fn get_returns_none_before_insert() {
+        let cache = PlanCache::new(2);
+        assert!(cache.get(1).is_none());
+    }
+
+    #[test]
+    fn get_returns_inserted_plan() {
+        let cache = PlanCache::new(2);
+        cache.insert(1, plan("{me{name}}"));
+        assert_eq!(cache.get(1), Some(plan("{me{name}}")));
+    }
+
+    #[test]
+    fn insert_evicts_least_recently_used_past_capacity() {
+        let cache = PlanCache::new(2);
+        cache.insert(1, plan("{a}"));
+        cache.insert(2, plan("{b}"));
+        // Touch key 1 so key 2 becomes the least-recently-used entry.
+        cache.get(1);
+        cache.insert(3, plan("{c}"));
+
+        assert!(cache.get(2).is_none());
+        assert_eq!(cache.get(1), Some(plan("{a}")));
+        assert_eq!(cache.get(3), Some(plan("{c}")));
+    }
+}
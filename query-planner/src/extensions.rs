@@ -0,0 +1,170 @@
+//! Observability hooks for the planner, mirroring async-graphql's `Extension`
+//! trait: implementors can time and observe each phase of `QueryPlanner::plan`.
+
+use crate::model::{PlanNode, QueryPlan};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Callbacks invoked around each phase of planning. All methods have no-op
+/// defaults so implementors only override the phases they care about.
+///
+/// A single extension instance is typically shared via `Arc` across every
+/// `plan`/`plan_cached` call on a planner, including concurrent ones, so it
+/// must not correlate its own hooks through shared mutable state (e.g. a
+/// `Mutex<bool>` set in `on_parse` and read back in `on_plan`) -- two in-flight
+/// calls would race and could suppress or leak each other's state. Instead,
+/// `on_parse` returns a per-call value that the planner threads back into
+/// `on_validate`/`on_plan` for that same `plan()` invocation.
+pub trait PlannerExtension: Send + Sync {
+    /// Returns per-call context (e.g. "is this introspection-only?") to be
+    /// passed to `on_validate`/`on_plan` for this same call.
+    fn on_parse(&self, _query: &str, _elapsed: Duration) -> bool {
+        false
+    }
+    fn on_validate(&self, _skip: bool, _errors: &[String], _elapsed: Duration) {}
+    fn on_plan(&self, _skip: bool, _plan: &QueryPlan, _elapsed: Duration) {}
+}
+
+/// True if `query`'s only top-level selections are introspection fields
+/// (`__schema`/`__type`), so extensions can skip logging noise from tooling.
+fn is_introspection_only(query: &str) -> bool {
+    let body = query
+        .splitn(2, '{')
+        .nth(1)
+        .unwrap_or("")
+        .trim_start();
+    body.starts_with("__schema") || body.starts_with("__type")
+}
+
+/// Records the normalized query and the resulting plan's fetch count, like
+/// async-graphql's `Logger` extension. Suppresses introspection-only operations.
+///
+/// `lines` is a shared accumulator (by design -- it's meant to collect output
+/// across every call), not correlation state between a single call's hooks;
+/// whether *this* call is introspection-only is threaded through the `skip`
+/// value returned by `on_parse` and passed back into `on_validate`/`on_plan`.
+#[derive(Default)]
+pub struct LoggingExtension {
+    lines: Mutex<Vec<String>>,
+}
+
+impl LoggingExtension {
+    /// Returns every line recorded so far, in order.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().clone()
+    }
+
+    fn record(&self, line: String) {
+        self.lines.lock().unwrap().push(line);
+    }
+}
+
+impl PlannerExtension for LoggingExtension {
+    fn on_parse(&self, query: &str, elapsed: Duration) -> bool {
+        let skip = is_introspection_only(query);
+        if !skip {
+            self.record(format!(
+                "parsed `{}` in {:?}",
+                query.split_whitespace().collect::<Vec<_>>().join(" "),
+                elapsed
+            ));
+        }
+        skip
+    }
+
+    fn on_validate(&self, skip: bool, errors: &[String], elapsed: Duration) {
+        if skip {
+            return;
+        }
+        self.record(format!("validated ({} errors) in {:?}", errors.len(), elapsed));
+    }
+
+    fn on_plan(&self, skip: bool, plan: &QueryPlan, elapsed: Duration) {
+        if skip {
+            return;
+        }
+        self.record(format!(
+            "planned {} fetch(es) in {:?}",
+            count_fetches(plan.node.as_ref()),
+            elapsed
+        ));
+    }
+}
+
+fn count_fetches(node: Option<&PlanNode>) -> usize {
+    match node {
+        None => 0,
+        Some(PlanNode::Fetch(_)) => 1,
+        Some(PlanNode::Sequence { nodes }) | Some(PlanNode::Parallel { nodes }) => {
+            nodes.iter().map(|n| count_fetches(Some(n))).sum()
+        }
+        Some(PlanNode::Flatten(flatten)) => count_fetches(Some(flatten.node.as_ref())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::FetchNode;
+
+    fn plan() -> QueryPlan {
+        QueryPlan {
+            node: Some(PlanNode::Fetch(FetchNode {
+                service_name: String::from("accounts"),
+                requires: None,
+                variable_usages: vec![],
+                operation: String::from("{me{name}}"),
+            })),
+        }
+    }
+
+    #[test]
+    fn on_parse_flags_introspection_only_queries() {
+        let ext = LoggingExtension::default();
+        assert!(ext.on_parse("{ __schema { types { name } } }", Duration::default()));
+        assert!(ext.on_parse("{ __type(name: \"Foo\") { name } }", Duration::default()));
+        assert!(!ext.on_parse("{ me { name } }", Duration::default()));
+    }
+
+    #[test]
+    fn skip_from_on_parse_suppresses_later_hooks() {
+        let ext = LoggingExtension::default();
+        let skip = ext.on_parse("{ __schema { types { name } } }", Duration::default());
+        ext.on_validate(skip, &[], Duration::default());
+        ext.on_plan(skip, &plan(), Duration::default());
+
+        assert_eq!(ext.lines(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn non_skipped_call_records_all_three_hooks_in_order() {
+        let ext = LoggingExtension::default();
+        let skip = ext.on_parse("{ me { name } }", Duration::default());
+        ext.on_validate(skip, &[], Duration::default());
+        ext.on_plan(skip, &plan(), Duration::default());
+
+        let lines = ext.lines();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("parsed"));
+        assert!(lines[1].starts_with("validated"));
+        assert!(lines[2].starts_with("planned"));
+    }
+
+    #[test]
+    fn concurrent_calls_do_not_leak_skip_state_across_each_other() {
+        // Regression guard for the race `on_parse`'s per-call return value avoids:
+        // an introspection call's `skip` must never suppress a concurrent
+        // non-introspection call's hooks, or vice versa.
+        let ext = LoggingExtension::default();
+        let skip_a = ext.on_parse("{ __schema { types { name } } }", Duration::default());
+        let skip_b = ext.on_parse("{ me { name } }", Duration::default());
+
+        ext.on_validate(skip_a, &[], Duration::default());
+        ext.on_validate(skip_b, &[], Duration::default());
+
+        let lines = ext.lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("parsed `{ me { name } }`"));
+        assert!(lines[1].starts_with("validated"));
+    }
+}
@@ -5,43 +5,260 @@ extern crate lazy_static;
 extern crate derive_builder;
 
 pub use crate::builder::build_query_plan;
+pub use crate::cache::PlanCache;
+pub use crate::extensions::{LoggingExtension, PlannerExtension};
 use crate::model::QueryPlan;
+use graphql_parser::query::{self, Definition};
 use graphql_parser::{parse_query, parse_schema, schema, ParseError};
+use std::sync::Arc;
+use std::time::Instant;
 
 #[macro_use]
 mod macros;
 mod autofrag;
 mod builder;
+mod cache;
 mod consts;
 mod context;
+mod extensions;
 mod federation;
 mod groups;
 mod helpers;
 pub mod model;
+mod validation;
 mod visitors;
 
+/// A line/column position within a source document, attached to errors that
+/// originate after parsing (validation and planning) so callers can point a user
+/// at the offending part of their query.
+#[derive(Debug, Clone, Copy)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
 #[derive(Debug)]
 pub enum QueryPlanError {
     FailedParsingSchema(ParseError),
     FailedParsingQuery(ParseError),
-    InvalidQuery(&'static str),
+    /// A single, structural reason the requested operation couldn't be
+    /// resolved (e.g. an unknown or missing `operationName`) -- as opposed to
+    /// [`QueryPlanError::ValidationFailed`], which reports every rule
+    /// violation found while validating a resolved operation against the
+    /// schema.
+    InvalidQuery(String),
+    /// Every rule violation `validation::validate` found, reported together
+    /// rather than stopping at the first one.
+    ValidationFailed(Vec<String>),
+    PlanningError { pos: Pos, message: String },
 }
 
 pub type Result<T> = std::result::Result<T, QueryPlanError>;
 
 pub struct QueryPlanner<'s> {
     schema: schema::Document<'s>,
+    extensions: Vec<Arc<dyn PlannerExtension>>,
+    cache: Option<PlanCache>,
 }
 
 impl<'s> QueryPlanner<'s> {
-    pub fn new(schema: &'s str) -> QueryPlanner<'s> {
-        let schema = parse_schema(schema).expect("failed parsing schema");
-        QueryPlanner { schema }
+    pub fn new(schema: &'s str) -> Result<QueryPlanner<'s>> {
+        let schema = parse_schema(schema).map_err(QueryPlanError::FailedParsingSchema)?;
+        Ok(QueryPlanner {
+            schema,
+            extensions: Vec::new(),
+            cache: None,
+        })
+    }
+
+    /// Registers an extension to observe the parse/validate/plan phases of
+    /// every subsequent call to `plan`.
+    pub fn with_extension(mut self, extension: Arc<dyn PlannerExtension>) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// Enables `plan_cached`, bounding it to `capacity` distinct signatures.
+    /// Gateways can trade memory for latency by tuning this.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache = Some(PlanCache::new(capacity));
+        self
     }
 
     pub fn plan(&self, query: &str, options: QueryPlanningOptions) -> Result<QueryPlan> {
-        let query = parse_query(query).expect("failed parsing query");
-        build_query_plan(&self.schema, &query, options)
+        let parse_start = Instant::now();
+        let parsed = parse_query(query).map_err(QueryPlanError::FailedParsingQuery)?;
+        let parse_elapsed = parse_start.elapsed();
+        let skips = self.on_parse(query, parse_elapsed);
+
+        self.plan_parsed(parsed, options, skips)
+    }
+
+    /// Like `plan`, but memoizes results in the cache enabled by
+    /// `with_cache_capacity`, keyed on a normalized signature of the query,
+    /// operation name, and options. Falls back to an uncached `plan` if no
+    /// cache was configured.
+    pub fn plan_cached(&self, query: &str, options: QueryPlanningOptions) -> Result<QueryPlan> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return self.plan(query, options),
+        };
+
+        let parse_start = Instant::now();
+        let parsed = parse_query(query).map_err(QueryPlanError::FailedParsingQuery)?;
+        let parse_elapsed = parse_start.elapsed();
+        let skips = self.on_parse(query, parse_elapsed);
+
+        let key = cache::signature(&parsed, options.operation_name.as_deref(), &options);
+        if let Some(plan) = cache.get(key) {
+            return Ok(plan);
+        }
+
+        let plan = self.plan_parsed(parsed, options, skips)?;
+        cache.insert(key, plan.clone());
+        Ok(plan)
+    }
+
+    /// Calls `on_parse` on every registered extension, returning the per-call
+    /// context each one hands back so it can be threaded into that same call's
+    /// `on_validate`/`on_plan` -- extensions are shared across concurrent calls
+    /// via `Arc`, so this context must live on the stack here, not in the
+    /// extension itself.
+    fn on_parse(&self, query: &str, elapsed: std::time::Duration) -> Vec<bool> {
+        self.extensions
+            .iter()
+            .map(|extension| extension.on_parse(query, elapsed))
+            .collect()
+    }
+
+    fn plan_parsed(
+        &self,
+        parsed: query::Document,
+        options: QueryPlanningOptions,
+        skips: Vec<bool>,
+    ) -> Result<QueryPlan> {
+        let validate_start = Instant::now();
+        let validation_errors = validation::validate(&self.schema, &parsed);
+        let validate_elapsed = validate_start.elapsed();
+        for (extension, &skip) in self.extensions.iter().zip(&skips) {
+            extension.on_validate(skip, &validation_errors, validate_elapsed);
+        }
+        if !validation_errors.is_empty() {
+            return Err(QueryPlanError::ValidationFailed(validation_errors));
+        }
+
+        // Resolve which operation we're planning for up front, exactly like
+        // async-graphql's `document.operations` lookup, so a multi-operation
+        // document plus an ambiguous/missing `operationName` fails fast instead of
+        // silently planning the wrong (or first) operation.
+        let operation = select_operation(&parsed, options.operation_name.as_deref())?;
+        let options = resolve_variable_defaults(operation, options);
+
+        let plan_start = Instant::now();
+        let plan = build_query_plan(&self.schema, &parsed, options)?;
+        let plan_elapsed = plan_start.elapsed();
+        for (extension, &skip) in self.extensions.iter().zip(&skips) {
+            extension.on_plan(skip, &plan, plan_elapsed);
+        }
+
+        Ok(plan)
+    }
+}
+
+/// Finds the `OperationDefinition` matching `operation_name`, or the sole
+/// operation in the document when no name is given. Errors if the document
+/// contains more than one operation and the name is missing or doesn't match
+/// exactly one of them.
+fn select_operation<'q>(
+    query: &'q query::Document<'q>,
+    operation_name: Option<&str>,
+) -> Result<&'q query::OperationDefinition<'q>> {
+    let operations: Vec<&query::OperationDefinition> = query
+        .definitions
+        .iter()
+        .filter_map(|def| match def {
+            Definition::Operation(op) => Some(op),
+            _ => None,
+        })
+        .collect();
+
+    match operation_name {
+        Some(name) => operations
+            .into_iter()
+            .find(|op| op.name.as_deref() == Some(name))
+            .ok_or_else(|| {
+                QueryPlanError::InvalidQuery(format!("Unknown operation named \"{}\"", name))
+            }),
+        None => match operations.len() {
+            1 => Ok(operations[0]),
+            0 => Err(QueryPlanError::InvalidQuery(String::from(
+                "Query document defines no operations",
+            ))),
+            _ => Err(QueryPlanError::PlanningError {
+                pos: Pos {
+                    line: operations[0].position.line,
+                    column: operations[0].position.column,
+                },
+                message: String::from(
+                    "Query document defines multiple operations; `operation_name` must be given",
+                ),
+            }),
+        },
+    }
+}
+
+/// Fills in `options.variables` with `operation`'s declared defaults for any
+/// variable the caller didn't supply a value for, exactly as a GraphQL
+/// executor would before running the operation -- otherwise a query that
+/// only works because of a `$limit: Int = 10`-style default would fail (or
+/// silently plan as if `$limit` were absent) whenever a caller omits it.
+fn resolve_variable_defaults(
+    operation: &query::OperationDefinition,
+    mut options: QueryPlanningOptions,
+) -> QueryPlanningOptions {
+    if operation
+        .variable_definitions
+        .iter()
+        .all(|var| var.default_value.is_none())
+    {
+        return options;
+    }
+
+    if !options.variables.is_object() {
+        options.variables = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let variables = options.variables.as_object_mut().unwrap();
+    for var in &operation.variable_definitions {
+        if let Some(default) = &var.default_value {
+            variables
+                .entry(var.name.to_string())
+                .or_insert_with(|| graphql_value_to_json(default));
+        }
+    }
+    options
+}
+
+/// Converts a parsed GraphQL [`query::Value`] literal (e.g. a variable's
+/// default) into the `serde_json::Value` that `QueryPlanningOptions::variables`
+/// is keyed in.
+fn graphql_value_to_json(value: &query::Value) -> serde_json::Value {
+    match value {
+        query::Value::Variable(_) => serde_json::Value::Null,
+        query::Value::Int(n) => serde_json::Value::from(n.as_i64()),
+        query::Value::Float(f) => serde_json::Value::from(*f),
+        query::Value::String(s) => serde_json::Value::String(s.clone()),
+        query::Value::Boolean(b) => serde_json::Value::Bool(*b),
+        query::Value::Null => serde_json::Value::Null,
+        query::Value::Enum(e) => serde_json::Value::String(e.to_string()),
+        query::Value::List(items) => {
+            serde_json::Value::Array(items.iter().map(graphql_value_to_json).collect())
+        }
+        query::Value::Object(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), graphql_value_to_json(value)))
+                .collect(),
+        ),
     }
 }
 
@@ -51,6 +268,10 @@ impl<'s> QueryPlanner<'s> {
 #[derive(Default, Builder, Debug)]
 pub struct QueryPlanningOptions {
     auto_fragmentization: bool,
+    #[builder(default)]
+    operation_name: Option<String>,
+    #[builder(default)]
+    variables: serde_json::Value,
 }
 
 #[cfg(test)]
@@ -90,7 +311,7 @@ mod tests {
 
         for dir in dirs {
             let schema = read_to_string(dir.join("csdl.graphql")).unwrap();
-            let planner = QueryPlanner::new(&schema);
+            let planner = QueryPlanner::new(&schema).unwrap();
             let feature_paths = read_dir(dir)
                 .unwrap()
                 .map(|res| res.map(|e| e.path()).unwrap())
@@ -0,0 +1,572 @@
+//! Schema-aware validation for parsed query documents, run before planning.
+//!
+//! Mirrors a subset of async-graphql's `validation::rules`: each rule walks the
+//! operation's selection set once and appends any violations it finds to a shared
+//! error list instead of bailing out on the first problem, so `QueryPlanner::plan`
+//! can report everything wrong with a query in a single pass.
+
+use graphql_parser::query::{
+    Definition, Document, Field, FragmentDefinition, InlineFragment, OperationDefinition,
+    Selection, SelectionSet, Txt, Value, VariableDefinition,
+};
+use graphql_parser::schema;
+use std::collections::HashMap;
+
+/// Validates `query` against `schema`, returning every error found. Does not
+/// short-circuit on the first failure -- callers get a full report.
+pub fn validate<'a>(schema: &'a schema::Document<'a>, query: &'a Document<'a>) -> Vec<String> {
+    let mut fragments = HashMap::new();
+    for definition in &query.definitions {
+        if let Definition::Fragment(frag) = definition {
+            fragments.insert(frag.name.as_ref(), frag);
+        }
+    }
+
+    let mut ctx = Context {
+        schema,
+        fragments,
+        errors: Vec::new(),
+    };
+
+    for definition in &query.definitions {
+        if let Definition::Operation(op) = definition {
+            ctx.validate_operation(op);
+        }
+    }
+
+    ctx.errors
+}
+
+struct Context<'a> {
+    schema: &'a schema::Document<'a>,
+    fragments: HashMap<&'a str, &'a FragmentDefinition<'a>>,
+    errors: Vec<String>,
+}
+
+impl<'a> Context<'a> {
+    fn validate_operation(&mut self, op: &'a OperationDefinition<'a>) {
+        known_directives(self.schema, &op.directives, &mut self.errors);
+        no_undefined_variables(op, &self.fragments, &mut self.errors);
+
+        let root_type_name = match op.kind.as_str() {
+            "mutation" => "Mutation",
+            "subscription" => "Subscription",
+            _ => "Query",
+        };
+
+        match find_composite_type(self.schema, root_type_name) {
+            Some(root) => self.walk_selection_set(root, &op.selection_set, &op.variable_definitions),
+            None => self
+                .errors
+                .push(format!("Schema has no root `{}` type", root_type_name)),
+        }
+    }
+
+    fn walk_selection_set(
+        &mut self,
+        parent_type: Composite<'a>,
+        selection_set: &'a SelectionSet<'a>,
+        variable_definitions: &'a [VariableDefinition<'a>],
+    ) {
+        for selection in &selection_set.items {
+            match selection {
+                Selection::Field(field) => self.validate_field(parent_type, field, variable_definitions),
+                Selection::InlineFragment(frag) => self.validate_inline_fragment(parent_type, frag, variable_definitions),
+                Selection::FragmentSpread(spread) => {
+                    match self.fragments.get(spread.fragment_name.as_ref()) {
+                        Some(frag) => {
+                            known_type_names(self.schema, frag.type_condition.as_ref(), &mut self.errors);
+                            if let Some(target) = find_composite_type(self.schema, frag.type_condition.as_ref()) {
+                                self.walk_selection_set(target, &frag.selection_set, variable_definitions);
+                            }
+                        }
+                        None => self
+                            .errors
+                            .push(format!("Unknown fragment \"{}\"", spread.fragment_name.as_ref())),
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_inline_fragment(
+        &mut self,
+        parent_type: Composite<'a>,
+        frag: &'a InlineFragment<'a>,
+        variable_definitions: &'a [VariableDefinition<'a>],
+    ) {
+        match &frag.type_condition {
+            Some(condition) => {
+                known_type_names(self.schema, condition.as_ref(), &mut self.errors);
+                match find_composite_type(self.schema, condition.as_ref()) {
+                    Some(target) => self.walk_selection_set(target, &frag.selection_set, variable_definitions),
+                    None => {}
+                }
+            }
+            None => self.walk_selection_set(parent_type, &frag.selection_set, variable_definitions),
+        }
+    }
+
+    fn validate_field(
+        &mut self,
+        parent_type: Composite<'a>,
+        field: &'a Field<'a>,
+        variable_definitions: &'a [VariableDefinition<'a>],
+    ) {
+        let field_name = field.name.as_ref();
+        if field_name == "__typename" {
+            return;
+        }
+
+        let field_def = match fields_on_correct_type(parent_type, field_name, &mut self.errors) {
+            Some(def) => def,
+            None => return,
+        };
+
+        known_argument_names(field_def, field, &mut self.errors);
+        arguments_of_correct_type(self.schema, field_def, field, &mut self.errors);
+
+        if let Some(target) = find_composite_type(self.schema, type_name(&field_def.field_type)) {
+            self.walk_selection_set(target, &field.selection_set, variable_definitions);
+        }
+    }
+}
+
+/// An object, interface, or union type -- anywhere a selection set can resolve
+/// fields against a parent type, it may be any of the three, since
+/// interface/union-typed fields are walked the same way as object-typed ones
+/// (the concrete object returned at runtime is only known at execution time).
+///
+/// A union has no fields of its own -- every member except `__typename` must
+/// come through a fragment naming one of its member types -- so
+/// `Composite::Union::fields()` is empty; that alone makes `validate_field`
+/// correctly reject a bare field selected directly on a union, the same way
+/// it would reject an undeclared field on an object or interface.
+#[derive(Clone, Copy)]
+enum Composite<'a> {
+    Object(&'a schema::ObjectType<'a>),
+    Interface(&'a schema::InterfaceType<'a>),
+    Union(&'a schema::UnionType<'a>),
+}
+
+impl<'a> Composite<'a> {
+    fn name(&self) -> &'a str {
+        match self {
+            Composite::Object(t) => t.name.as_ref(),
+            Composite::Interface(t) => t.name.as_ref(),
+            Composite::Union(t) => t.name.as_ref(),
+        }
+    }
+
+    fn fields(&self) -> &'a [schema::Field<'a>] {
+        match self {
+            Composite::Object(t) => &t.fields,
+            Composite::Interface(t) => &t.fields,
+            Composite::Union(_) => &[],
+        }
+    }
+}
+
+/// *fields-on-correct-type*: every `Field.name` must be declared on its resolved
+/// parent object or interface type.
+fn fields_on_correct_type<'a>(
+    parent_type: Composite<'a>,
+    field_name: &str,
+    errors: &mut Vec<String>,
+) -> Option<&'a schema::Field<'a>> {
+    match parent_type.fields().iter().find(|f| f.name.as_ref() == field_name) {
+        Some(field) => Some(field),
+        None => {
+            errors.push(format!(
+                "Cannot query field \"{}\" on type \"{}\"",
+                field_name,
+                parent_type.name()
+            ));
+            None
+        }
+    }
+}
+
+/// *known-type-names*: every type condition must name an existing composite type.
+fn known_type_names(schema: &schema::Document, type_name: &str, errors: &mut Vec<String>) {
+    if find_composite_type(schema, type_name).is_none() {
+        errors.push(format!("Unknown type \"{}\"", type_name));
+    }
+}
+
+/// *known-argument-names*: every argument passed to a field must be declared on it.
+fn known_argument_names(field_def: &schema::Field, field: &Field, errors: &mut Vec<String>) {
+    for (name, _) in &field.arguments {
+        if !field_def.arguments.iter().any(|a| a.name.as_ref() == name.as_ref()) {
+            errors.push(format!(
+                "Unknown argument \"{}\" on field \"{}\"",
+                name.as_ref(),
+                field_def.name.as_ref()
+            ));
+        }
+    }
+}
+
+/// *arguments-of-correct-type*: each argument's value matches its declared input
+/// type, recursing into list and input object values.
+fn arguments_of_correct_type(
+    schema: &schema::Document,
+    field_def: &schema::Field,
+    field: &Field,
+    errors: &mut Vec<String>,
+) {
+    for (name, value) in &field.arguments {
+        if let Some(arg_def) = field_def.arguments.iter().find(|a| a.name.as_ref() == name.as_ref()) {
+            check_value_type(schema, name.as_ref(), value, &arg_def.value_type, errors);
+        }
+    }
+}
+
+/// Checks `value` against `expected`, recursing through `NonNullType`/`ListType`
+/// wrappers and, for named types, against the schema's scalar/enum/input-object
+/// definition. Variables are left unchecked -- their value isn't known until
+/// execution time, and their declared type is covered separately.
+fn check_value_type(
+    schema: &schema::Document,
+    arg_name: &str,
+    value: &Value,
+    expected: &schema::Type,
+    errors: &mut Vec<String>,
+) {
+    use schema::Type;
+
+    if matches!(value, Value::Variable(_)) {
+        return;
+    }
+
+    match expected {
+        Type::NonNullType(inner) => {
+            if matches!(value, Value::Null) {
+                errors.push(format!(
+                    "Argument \"{}\" of required type \"{}\" was not provided",
+                    arg_name, expected
+                ));
+                return;
+            }
+            check_value_type(schema, arg_name, value, inner, errors);
+        }
+        Type::ListType(inner) => match value {
+            Value::Null => {}
+            Value::List(items) => {
+                for item in items {
+                    check_value_type(schema, arg_name, item, inner, errors);
+                }
+            }
+            // A single value is coerced into a one-element list, as the
+            // GraphQL spec requires.
+            other => check_value_type(schema, arg_name, other, inner, errors),
+        },
+        Type::NamedType(name) => {
+            check_named_value_type(schema, arg_name, value, name.as_ref(), errors)
+        }
+    }
+}
+
+fn check_named_value_type(
+    schema: &schema::Document,
+    arg_name: &str,
+    value: &Value,
+    type_name: &str,
+    errors: &mut Vec<String>,
+) {
+    if matches!(value, Value::Null) {
+        return;
+    }
+
+    match type_name {
+        "Int" => {
+            if !matches!(value, Value::Int(_)) {
+                errors.push(type_mismatch(arg_name, type_name));
+            }
+        }
+        "Float" => {
+            if !matches!(value, Value::Float(_) | Value::Int(_)) {
+                errors.push(type_mismatch(arg_name, type_name));
+            }
+        }
+        "String" => {
+            if !matches!(value, Value::String(_)) {
+                errors.push(type_mismatch(arg_name, type_name));
+            }
+        }
+        // Per spec, ID accepts both string and integer literals.
+        "ID" => {
+            if !matches!(value, Value::String(_) | Value::Int(_)) {
+                errors.push(type_mismatch(arg_name, type_name));
+            }
+        }
+        "Boolean" => {
+            if !matches!(value, Value::Boolean(_)) {
+                errors.push(type_mismatch(arg_name, type_name));
+            }
+        }
+        _ => match find_type_definition(schema, type_name) {
+            Some(schema::TypeDefinition::Enum(enum_type)) => match value {
+                Value::Enum(name) => {
+                    if !enum_type.values.iter().any(|v| v.name.as_ref() == name.as_ref()) {
+                        errors.push(format!(
+                            "Value \"{}\" does not exist in \"{}\"",
+                            name.as_ref(),
+                            type_name
+                        ));
+                    }
+                }
+                _ => errors.push(type_mismatch(arg_name, type_name)),
+            },
+            Some(schema::TypeDefinition::InputObject(input_type)) => match value {
+                Value::Object(fields) => {
+                    for (field_name, field_value) in fields.iter() {
+                        match input_type.fields.iter().find(|f| f.name.as_ref() == field_name.as_ref()) {
+                            Some(input_field) => check_value_type(
+                                schema,
+                                field_name.as_ref(),
+                                field_value,
+                                &input_field.value_type,
+                                errors,
+                            ),
+                            None => errors.push(format!(
+                                "Unknown field \"{}\" on input type \"{}\"",
+                                field_name.as_ref(),
+                                type_name
+                            )),
+                        }
+                    }
+                }
+                _ => errors.push(type_mismatch(arg_name, type_name)),
+            },
+            // An unresolvable type name is already reported by known-type-names
+            // style rules where the type is referenced; don't double-report here.
+            _ => {}
+        },
+    }
+}
+
+fn type_mismatch(arg_name: &str, type_name: &str) -> String {
+    format!(
+        "Argument \"{}\" has an invalid value; expected type \"{}\"",
+        arg_name, type_name
+    )
+}
+
+fn find_type_definition<'a>(schema: &'a schema::Document<'a>, name: &str) -> Option<&'a schema::TypeDefinition<'a>> {
+    schema.definitions.iter().find_map(|def| match def {
+        schema::Definition::TypeDefinition(typ) if type_definition_name(typ) == name => Some(typ),
+        _ => None,
+    })
+}
+
+fn type_definition_name<'a>(typ: &'a schema::TypeDefinition<'a>) -> &'a str {
+    match typ {
+        schema::TypeDefinition::Scalar(t) => t.name.as_ref(),
+        schema::TypeDefinition::Object(t) => t.name.as_ref(),
+        schema::TypeDefinition::Interface(t) => t.name.as_ref(),
+        schema::TypeDefinition::Union(t) => t.name.as_ref(),
+        schema::TypeDefinition::Enum(t) => t.name.as_ref(),
+        schema::TypeDefinition::InputObject(t) => t.name.as_ref(),
+    }
+}
+
+/// *known-directives*: every `@directive` used must be declared in the schema,
+/// save for the handful the spec guarantees exist regardless of what the
+/// schema itself declares.
+fn known_directives(
+    schema: &schema::Document,
+    directives: &[graphql_parser::query::Directive],
+    errors: &mut Vec<String>,
+) {
+    const BUILTIN: &[&str] = &["skip", "include", "deprecated"];
+    for directive in directives {
+        let name = directive.name.as_ref();
+        if BUILTIN.contains(&name) {
+            continue;
+        }
+        let declared = schema.definitions.iter().any(|def| {
+            matches!(def, schema::Definition::DirectiveDefinition(d) if d.name.as_ref() == name)
+        });
+        if !declared {
+            errors.push(format!("Unknown directive \"@{}\"", name));
+        }
+    }
+}
+
+/// *no-undefined-variables*: every `Value::Variable` used in the operation's
+/// selection set, including through fragment spreads, must appear in its
+/// `variable_definitions`.
+fn no_undefined_variables<'a>(
+    op: &'a OperationDefinition<'a>,
+    fragments: &HashMap<&'a str, &'a FragmentDefinition<'a>>,
+    errors: &mut Vec<String>,
+) {
+    let mut used = Vec::new();
+    let mut visited = std::collections::HashSet::new();
+    collect_variables(&op.selection_set, fragments, &mut visited, &mut used);
+
+    for name in used {
+        if !op.variable_definitions.iter().any(|v| v.name.as_ref() == name) {
+            errors.push(format!(
+                "Variable \"${}\" is not defined by operation \"{}\"",
+                name,
+                op.name.as_deref().unwrap_or("<anonymous>")
+            ));
+        }
+    }
+}
+
+fn collect_variables<'a>(
+    selection_set: &'a SelectionSet<'a>,
+    fragments: &HashMap<&'a str, &'a FragmentDefinition<'a>>,
+    visited: &mut std::collections::HashSet<&'a str>,
+    used: &mut Vec<&'a str>,
+) {
+    for selection in &selection_set.items {
+        match selection {
+            Selection::Field(field) => {
+                for (_, value) in &field.arguments {
+                    collect_variables_in_value(value, used);
+                }
+                collect_variables(&field.selection_set, fragments, visited, used);
+            }
+            Selection::InlineFragment(frag) => {
+                collect_variables(&frag.selection_set, fragments, visited, used);
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.fragment_name.as_ref();
+                // Fragments can reference each other (though not cyclically in a
+                // valid document); guard against a malformed cycle sending us
+                // into an infinite recursion instead of a validation error.
+                if !visited.insert(name) {
+                    continue;
+                }
+                if let Some(frag) = fragments.get(name) {
+                    collect_variables(&frag.selection_set, fragments, visited, used);
+                }
+            }
+        }
+    }
+}
+
+fn collect_variables_in_value<'a>(value: &'a Value<'a>, used: &mut Vec<&'a str>) {
+    match value {
+        Value::Variable(name) => used.push(name.as_ref()),
+        Value::List(items) => {
+            for item in items {
+                collect_variables_in_value(item, used);
+            }
+        }
+        Value::Object(fields) => {
+            for (_, v) in fields.iter() {
+                collect_variables_in_value(v, used);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn find_composite_type<'a>(schema: &'a schema::Document<'a>, name: &str) -> Option<Composite<'a>> {
+    schema.definitions.iter().find_map(|def| match def {
+        schema::Definition::TypeDefinition(schema::TypeDefinition::Object(obj)) if obj.name.as_ref() == name => {
+            Some(Composite::Object(obj))
+        }
+        schema::Definition::TypeDefinition(schema::TypeDefinition::Interface(iface))
+            if iface.name.as_ref() == name =>
+        {
+            Some(Composite::Interface(iface))
+        }
+        schema::Definition::TypeDefinition(schema::TypeDefinition::Union(union_type))
+            if union_type.name.as_ref() == name =>
+        {
+            Some(Composite::Union(union_type))
+        }
+        _ => None,
+    })
+}
+
+fn type_name<'a>(typ: &'a graphql_parser::query::Type<'a>) -> &'a str {
+    use graphql_parser::query::Type;
+    match typ {
+        Type::NamedType(name) => name.as_ref(),
+        Type::ListType(inner) => type_name(inner),
+        Type::NonNullType(inner) => type_name(inner),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser::{parse_query, parse_schema};
+
+    const SCHEMA: &str = r#"
+        type Query {
+            entity(id: ID!): Entity
+            node(id: ID!): Node
+        }
+
+        union Entity = Account | Product
+
+        type Account {
+            id: ID!
+            name: String!
+        }
+
+        type Product {
+            id: ID!
+            price: Int!
+        }
+
+        interface Node {
+            id: ID!
+        }
+    "#;
+
+    fn validate_str(query: &str) -> Vec<String> {
+        let schema = parse_schema(SCHEMA).unwrap();
+        let doc = parse_query(query).unwrap();
+        validate(&schema, &doc)
+    }
+
+    #[test]
+    fn accepts_fragment_spread_on_union_member() {
+        let errors = validate_str(
+            r#"{ entity(id: "1") { ... on Account { name } ... on Product { price } } }"#,
+        );
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_bare_field_selected_directly_on_union() {
+        let errors = validate_str(r#"{ entity(id: "1") { name } }"#);
+        assert_eq!(errors, vec!["Cannot query field \"name\" on type \"Entity\""]);
+    }
+
+    #[test]
+    fn rejects_unknown_member_type_condition_on_union() {
+        let errors = validate_str(r#"{ entity(id: "1") { ... on Nonexistent { id } } }"#);
+        assert_eq!(errors, vec!["Unknown type \"Nonexistent\""]);
+    }
+
+    #[test]
+    fn id_argument_accepts_string_literal() {
+        let errors = validate_str(r#"{ node(id: "1") { id } }"#);
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn id_argument_accepts_integer_literal() {
+        let errors = validate_str(r#"{ node(id: 1) { id } }"#);
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn id_argument_rejects_boolean_literal() {
+        let errors = validate_str(r#"{ node(id: true) { id } }"#);
+        assert_eq!(
+            errors,
+            vec!["Argument \"id\" has an invalid value; expected type \"ID\""]
+        );
+    }
+}